@@ -1,14 +1,12 @@
-#[cfg(feature = "multi-user")]
-use oso::ToPolar;
-#[cfg(feature = "multi-user")]
-use std::fmt::Display;
-
 use std::collections::HashMap;
 use std::fmt;
 use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
 
 use crate::{commons::{KrillResult, error::Error}, constants::ACTOR_DEF_ANON, daemon::auth::Auth};
-use crate::daemon::auth::policy::AuthPolicy;
+use crate::daemon::auth::authorizer::Authorizer;
+use crate::daemon::auth::roles::RoleRegistry;
 
 #[derive(Clone, Eq, PartialEq)]
 pub enum ActorName {
@@ -25,17 +23,135 @@ impl ActorName {
     }
 }
 
+/// The identity a policy evaluates permissions against, as distinct from
+/// the credential that authenticated the request. `uid` is the primary
+/// user id; `subuid` optionally narrows or widens permissions for a scoped
+/// session (e.g. `+admin`, `+dashboard`); `realm` records the origin or
+/// provider the account came from. A single authenticated user can thus
+/// present multiple scoped actors whose permissions differ.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AuthorizationIdentity {
+    pub uid: String,
+    pub subuid: Option<String>,
+    pub realm: Option<String>,
+}
+
+impl AuthorizationIdentity {
+    /// The backward-compatible default: `uid` mirrors the authentication
+    /// name, with no sub-user scope or realm.
+    pub fn for_name(uid: impl Into<String>) -> Self {
+        AuthorizationIdentity {
+            uid: uid.into(),
+            subuid: None,
+            realm: None,
+        }
+    }
+
+    /// An empty identity, usable in `const` contexts (e.g. the `anonymous`
+    /// and `system` actor definitions) where `uid` isn't known yet.
+    /// `Actor::uid()` falls back to `Actor::name()` while `uid` is empty.
+    const fn unset() -> Self {
+        AuthorizationIdentity {
+            uid: String::new(),
+            subuid: None,
+            realm: None,
+        }
+    }
+}
+
+/// Why an actor cannot currently be granted access, distinct from a plain
+/// `false` verdict from a policy. Lets the HTTP layer tell a client whether
+/// to re-login outright, back off and retry, or silently refresh its
+/// existing session.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AuthError {
+    /// The credential presented was rejected outright; the client must
+    /// re-authenticate from scratch.
+    InvalidCredentials(String),
+    /// The credential has expired. When `soft_logout` is true the client
+    /// may reuse its existing device/session id to obtain a fresh token
+    /// rather than forcing the user through a full login.
+    TokenExpired { soft_logout: bool },
+    /// Too many attempts; the client should wait `retry_after` before
+    /// trying again.
+    RateLimited { retry_after: Duration },
+    /// The credential is valid but is not permitted to act at all.
+    Forbidden,
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthError::InvalidCredentials(msg) => write!(f, "invalid credentials: {}", msg),
+            AuthError::TokenExpired { soft_logout } => {
+                write!(f, "token expired (soft_logout={})", soft_logout)
+            }
+            AuthError::RateLimited { retry_after } => {
+                write!(f, "rate limited, retry after {:?}", retry_after)
+            }
+            AuthError::Forbidden => write!(f, "forbidden"),
+        }
+    }
+}
+
+/// The value of a single user-defined attribute. Most attributes are a
+/// single string, but some (e.g. "this actor belongs to CAs ca1, ca2, ca3")
+/// are naturally a list, and a few are naturally a flag.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AttrValue {
+    Scalar(String),
+    List(Vec<String>),
+    Bool(bool),
+}
+
+impl AttrValue {
+    /// All values this attribute holds, as strings.
+    pub fn as_list(&self) -> Vec<String> {
+        match self {
+            AttrValue::Scalar(s) => vec![s.clone()],
+            AttrValue::List(list) => list.clone(),
+            AttrValue::Bool(b) => vec![b.to_string()],
+        }
+    }
+
+    /// Collapses back to the plain scalar API that predates multi-valued
+    /// attributes. A single-element list collapses to that element; a list
+    /// with more than one value returns `None` rather than silently
+    /// dropping the rest, so unmigrated callers see "unset" instead of a
+    /// wrong single value.
+    pub fn as_scalar(&self) -> Option<String> {
+        match self {
+            AttrValue::Scalar(s) => Some(s.clone()),
+            AttrValue::List(list) if list.len() == 1 => list.first().cloned(),
+            AttrValue::List(_) => None,
+            AttrValue::Bool(b) => Some(b.to_string()),
+        }
+    }
+
+    pub fn contains(&self, value: &str) -> bool {
+        self.as_list().iter().any(|v| v == value)
+    }
+}
+
+impl From<String> for AttrValue {
+    fn from(s: String) -> Self {
+        AttrValue::Scalar(s)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum Attributes {
     None,
     RoleOnly(&'static str),
-    UserDefined(HashMap<String, String>)
+    UserDefined(HashMap<String, AttrValue>)
 }
 
 impl Attributes {
     pub fn as_map(&self) -> HashMap<String, String> {
         match &self {
-            Attributes::UserDefined(map) => map.clone(),
+            Attributes::UserDefined(map) => map.iter()
+                .filter_map(|(k, v)| v.as_scalar().map(|s| (k.clone(), s)))
+                .collect(),
             Attributes::RoleOnly(role) => {
                 let mut map = HashMap::new();
                 map.insert("role".to_string(), role.to_string());
@@ -52,13 +168,36 @@ pub struct ActorDef {
     pub is_user: bool,
     pub attributes: Attributes,
     pub new_auth: Option<Auth>,
-    pub auth_error: Option<String>,
+    pub auth_error: Option<AuthError>,
+    pub role_registry: Option<Arc<RoleRegistry>>,
+    pub auth_identity: AuthorizationIdentity,
 }
 
 impl ActorDef {
-    // store an error string instead of an Error because Error cannot be cloned.
+    // store an AuthError; is_allowed maps it onto the matching Error variant.
     pub fn with_auth_error(mut self, error_msg: String) -> Self {
-        self.auth_error = Some(error_msg);
+        self.auth_error = Some(AuthError::InvalidCredentials(error_msg));
+        self
+    }
+
+    /// Like `with_auth_error`, but for callers that already know which
+    /// structured [`AuthError`] applies (e.g. rate limiting, soft-logout).
+    pub fn with_structured_auth_error(mut self, auth_error: AuthError) -> Self {
+        self.auth_error = Some(auth_error);
+        self
+    }
+
+    /// Attaches a [`RoleRegistry`] so `role` attributes resolve to an
+    /// inherited, wildcard-aware effective permission set.
+    pub fn with_role_registry(mut self, role_registry: Arc<RoleRegistry>) -> Self {
+        self.role_registry = Some(role_registry);
+        self
+    }
+
+    /// Overrides the authorization identity, e.g. to scope this actor to a
+    /// `subuid` or to record the `realm` it authenticated through.
+    pub fn with_auth_identity(mut self, auth_identity: AuthorizationIdentity) -> Self {
+        self.auth_identity = auth_identity;
         self
     }
 }
@@ -69,8 +208,10 @@ pub struct Actor {
     is_user: bool,
     attributes: Attributes,
     new_auth: Option<Auth>,
-    policy: Option<AuthPolicy>,
-    auth_error: Option<String>,
+    authorizer: Option<Arc<dyn Authorizer>>,
+    auth_error: Option<AuthError>,
+    role_registry: Option<Arc<RoleRegistry>>,
+    auth_identity: AuthorizationIdentity,
 }
 
 impl PartialEq for Actor {
@@ -97,6 +238,8 @@ impl Actor {
             attributes: Attributes::None,
             new_auth: None,
             auth_error: None,
+            role_registry: None,
+            auth_identity: AuthorizationIdentity::unset(),
         }
     }
 
@@ -107,16 +250,30 @@ impl Actor {
             is_user: false,
             new_auth: None,
             auth_error: None,
+            role_registry: None,
+            auth_identity: AuthorizationIdentity::unset(),
         }
     }
 
     pub fn user(name: String, attributes: &HashMap<String, String>, new_auth: Option<Auth>) -> ActorDef {
+        let attributes = attributes.iter()
+            .map(|(k, v)| (k.clone(), AttrValue::Scalar(v.clone())))
+            .collect();
+        Actor::user_with_attributes(name, attributes, new_auth)
+    }
+
+    /// Like `user`, but for attributes that are naturally multi-valued
+    /// (e.g. group or CA membership lists) rather than a single string.
+    pub fn user_with_attributes(name: String, attributes: HashMap<String, AttrValue>, new_auth: Option<Auth>) -> ActorDef {
+        let auth_identity = AuthorizationIdentity::for_name(name.clone());
         ActorDef {
             name: ActorName::AsString(name),
             is_user: true,
-            attributes: Attributes::UserDefined(attributes.clone()),
+            attributes: Attributes::UserDefined(attributes),
             new_auth,
             auth_error: None,
+            role_registry: None,
+            auth_identity,
         }
     }
 
@@ -128,30 +285,38 @@ impl Actor {
             attributes: repr.attributes.clone(),
             new_auth: None,
             auth_error: None,
-            policy: None,
+            authorizer: None,
+            role_registry: repr.role_registry.clone(),
+            auth_identity: repr.auth_identity.clone(),
         }
     }
 
     /// Only for use in testing
     pub fn test_from_details(name: String, attrs: HashMap<String, String>) -> Actor {
+        let auth_identity = AuthorizationIdentity::for_name(name.clone());
+        let attrs = attrs.into_iter().map(|(k, v)| (k, AttrValue::Scalar(v))).collect();
         Actor {
             name: ActorName::AsString(name),
             attributes: Attributes::UserDefined(attrs),
             is_user: false,
             new_auth: None,
             auth_error: None,
-            policy: None,
+            authorizer: None,
+            role_registry: None,
+            auth_identity,
         }
     }
 
-    pub fn new(repr: &ActorDef, policy: AuthPolicy) -> Actor {
+    pub fn new(repr: &ActorDef, authorizer: Arc<dyn Authorizer>) -> Actor {
         Actor {
             name: repr.name.clone(),
             is_user: repr.is_user,
             attributes: repr.attributes.clone(),
             new_auth: repr.new_auth.clone(),
             auth_error: repr.auth_error.clone(),
-            policy: Some(policy),
+            authorizer: Some(authorizer),
+            role_registry: repr.role_registry.clone(),
+            auth_identity: repr.auth_identity.clone(),
         }
     }
 
@@ -173,17 +338,60 @@ impl Actor {
 
     pub fn attribute(&self, attr_name: String) -> Option<String> {
         match &self.attributes {
-            Attributes::UserDefined(map)                       => map.get(&attr_name).cloned(),
+            Attributes::UserDefined(map)                       => map.get(&attr_name).and_then(AttrValue::as_scalar),
             Attributes::RoleOnly(role) if &attr_name == "role" => Some(role.to_string()),
             Attributes::RoleOnly(_)                            => None,
             Attributes::None                                   => None,
         }
     }
 
+    /// All values of `attr_name`, e.g. the list of CA handles an actor's
+    /// `cas` attribute grants access to. Empty if the attribute is unset.
+    pub fn attribute_values(&self, attr_name: &str) -> Vec<String> {
+        match &self.attributes {
+            Attributes::UserDefined(map)                       => map.get(attr_name).map(AttrValue::as_list).unwrap_or_default(),
+            Attributes::RoleOnly(role) if attr_name == "role"  => vec![role.to_string()],
+            Attributes::RoleOnly(_)                            => Vec::new(),
+            Attributes::None                                   => Vec::new(),
+        }
+    }
+
+    /// True if `attr_name` is set and its values include `value`, e.g.
+    /// "does the actor's `cas` attribute contain this resource's CA handle".
+    pub fn has_attribute_value(&self, attr_name: &str, value: &str) -> bool {
+        match &self.attributes {
+            Attributes::UserDefined(map)                      => map.get(attr_name).map(|v| v.contains(value)).unwrap_or(false),
+            Attributes::RoleOnly(role) if attr_name == "role"  => *role == value,
+            Attributes::RoleOnly(_)                            => false,
+            Attributes::None                                   => false,
+        }
+    }
+
     pub fn name(&self) -> &str {
         self.name.as_str()
     }
 
+    /// The primary user id permissions are evaluated against. Defaults to
+    /// `name()` unless a distinct [`AuthorizationIdentity`] was set.
+    pub fn uid(&self) -> &str {
+        if self.auth_identity.uid.is_empty() {
+            self.name()
+        } else {
+            &self.auth_identity.uid
+        }
+    }
+
+    /// The sub-user scope narrowing or widening this actor's permissions,
+    /// if any (e.g. `+admin`, `+dashboard`).
+    pub fn subuid(&self) -> Option<&str> {
+        self.auth_identity.subuid.as_deref()
+    }
+
+    /// The origin/provider this actor's account came from, if known.
+    pub fn realm(&self) -> Option<&str> {
+        self.auth_identity.realm.as_deref()
+    }
+
     #[cfg(not(feature = "multi-user"))]
     pub fn is_allowed<A, R>(&self, _: A, _: R) -> KrillResult<bool> {
         Ok(true)
@@ -193,43 +401,65 @@ impl Actor {
     pub fn is_allowed<A, R>(&self, action: A, resource: R)
          -> KrillResult<bool>
     where
-        A: ToPolar + Display + Clone,
-        R: ToPolar + Display + Clone,
+        A: AsRef<str>,
+        R: AsRef<str>,
     {
-        if let Some(error_msg) = &self.auth_error {
+        let action = action.as_ref();
+        let resource = resource.as_ref();
+
+        if let Some(auth_error) = &self.auth_error {
             trace!("Unable to check access: actor={}, action={}, resource={}: {}",
-                self.name(), &action, &resource, &error_msg);
-            return Err(Error::ApiInvalidCredentials(error_msg.clone()));
+                self.name(), action, resource, auth_error);
+            return Err(match auth_error {
+                AuthError::InvalidCredentials(msg) => Error::ApiInvalidCredentials(msg.clone()),
+                AuthError::TokenExpired { soft_logout } => Error::ApiTokenExpired { soft_logout: *soft_logout },
+                AuthError::RateLimited { retry_after } => Error::ApiRateLimited { retry_after: *retry_after },
+                AuthError::Forbidden => Error::ApiForbidden,
+            });
         }
 
-        match &self.policy {
-            Some(policy) => {
-                match policy.is_allowed(self.clone(), action.clone(), resource.clone()) {
+        // A subuid scopes an actor down to (or up to) a narrower set of
+        // permissions that only the configured Authorizer can evaluate, so
+        // a subuid-scoped actor always falls through to it rather than
+        // taking the coarser role-registry short-circuit below.
+        if let (Some(role_registry), None) = (&self.role_registry, self.subuid()) {
+            if let Some(role) = self.attribute("role".to_string()) {
+                if role_registry.is_permitted(&role, resource, action) {
+                    trace!("Access granted by role '{}': actor={}, action={}, resource={}",
+                        role, self.name(), action, resource);
+                    return Ok(true);
+                }
+            }
+        }
+
+        match &self.authorizer {
+            Some(authorizer) => {
+                match authorizer.enforce(self, resource, action) {
                     Ok(allowed) => {
                         if log_enabled!(log::Level::Trace) {
                             if allowed {
                                 trace!("Access granted: actor={}, action={}, resource={}",
-                                    self.name(), &action, &resource);
+                                    self.name(), action, resource);
                             } else {
                                 trace!("Access denied: actor={:?}, action={}, resource={}",
-                                    self, &action, &resource);
+                                    self, action, resource);
                             }
                         }
                         Ok(allowed)
                     },
                     Err(err) => {
                         error!("Unable to check access: actor={}, action={}, resource={}: {}",
-                            self.name(), &action, &resource, err);
+                            self.name(), action, resource, err);
                         Ok(false)
                     }
                 }
             },
             None => {
-                // Auth policy is required, can only be omitted for use by test
-                // rules inside an Oso policy. We should never get here, but we
-                // don't want to crash Krill by calling unreachable!().
+                // An authorizer is required, can only be omitted for use by test
+                // rules inside a policy. We should never get here, but we don't
+                // want to crash Krill by calling unreachable!().
                 error!("Unable to check access: actor={}, action={}, resource={}: {}",
-                    self.name(), &action, &resource, "Internal error: missing policy");
+                    self.name(), action, resource, "Internal error: missing authorizer");
                 Ok(false)
             }
         }
@@ -247,4 +477,133 @@ impl fmt::Debug for Actor {
         write!(f, "Actor(name={:?}, is_user={}, attr={:?})",
             self.name(), self.is_user, self.attributes)
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "multi-user")]
+    struct FakeAuthorizer {
+        allow: bool,
+        calls: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[cfg(feature = "multi-user")]
+    impl FakeAuthorizer {
+        fn new(allow: bool) -> Arc<Self> {
+            Arc::new(FakeAuthorizer { allow, calls: std::sync::Mutex::new(Vec::new()) })
+        }
+
+        fn call_count(&self) -> usize {
+            self.calls.lock().unwrap().len()
+        }
+    }
+
+    #[cfg(feature = "multi-user")]
+    impl Authorizer for FakeAuthorizer {
+        fn enforce(&self, actor: &Actor, resource: &str, action: &str) -> KrillResult<bool> {
+            self.calls.lock().unwrap().push(format!("{}:{}:{}", actor.name(), resource, action));
+            Ok(self.allow)
+        }
+    }
+
+    #[test]
+    fn uid_falls_back_to_name_for_const_actors() {
+        let anon = Actor::test_from_def(&Actor::anonymous());
+        assert_eq!(anon.uid(), "anonymous");
+
+        let system = Actor::test_from_def(&Actor::system("testbed", "admin"));
+        assert_eq!(system.uid(), "testbed");
+    }
+
+    #[cfg(feature = "multi-user")]
+    #[test]
+    fn is_allowed_maps_structured_auth_errors_to_api_errors() {
+        let actor = Actor::test_from_def(
+            &Actor::anonymous().with_auth_error("bad credentials".to_string())
+        );
+        assert_eq!(
+            actor.is_allowed("action", "resource").unwrap_err(),
+            Error::ApiInvalidCredentials("bad credentials".to_string())
+        );
+
+        let actor = Actor::test_from_def(
+            &Actor::anonymous().with_structured_auth_error(AuthError::TokenExpired { soft_logout: true })
+        );
+        assert_eq!(
+            actor.is_allowed("action", "resource").unwrap_err(),
+            Error::ApiTokenExpired { soft_logout: true }
+        );
+
+        let actor = Actor::test_from_def(
+            &Actor::anonymous().with_structured_auth_error(AuthError::RateLimited { retry_after: Duration::from_secs(30) })
+        );
+        assert_eq!(
+            actor.is_allowed("action", "resource").unwrap_err(),
+            Error::ApiRateLimited { retry_after: Duration::from_secs(30) }
+        );
+
+        let actor = Actor::test_from_def(
+            &Actor::anonymous().with_structured_auth_error(AuthError::Forbidden)
+        );
+        assert_eq!(actor.is_allowed("action", "resource").unwrap_err(), Error::ApiForbidden);
+    }
+
+    #[cfg(feature = "multi-user")]
+    fn role_registry_granting(role: &str, resource: &str, action: &str) -> Arc<crate::daemon::auth::roles::RoleRegistry> {
+        use crate::daemon::auth::roles::{Role, RoleRegistry};
+        Arc::new(RoleRegistry::from_roles(vec![
+            Role::new(role, vec![], vec![format!("{}.{}", resource, action)]),
+        ]))
+    }
+
+    #[cfg(feature = "multi-user")]
+    #[test]
+    fn is_allowed_short_circuits_on_role_grant_without_calling_authorizer() {
+        let mut attrs = HashMap::new();
+        attrs.insert("role".to_string(), AttrValue::Scalar("reader".to_string()));
+        let def = Actor::user_with_attributes("alice".to_string(), attrs, None)
+            .with_role_registry(role_registry_granting("reader", "ca", "list"));
+        let authorizer = FakeAuthorizer::new(false);
+        let actor = Actor::new(&def, authorizer.clone());
+
+        assert_eq!(actor.is_allowed("list", "ca").unwrap(), true);
+        assert_eq!(authorizer.call_count(), 0);
+    }
+
+    #[cfg(feature = "multi-user")]
+    #[test]
+    fn is_allowed_falls_through_to_authorizer_for_subuid_scoped_actor_despite_role_grant() {
+        let mut attrs = HashMap::new();
+        attrs.insert("role".to_string(), AttrValue::Scalar("reader".to_string()));
+        let def = Actor::user_with_attributes("alice".to_string(), attrs, None)
+            .with_role_registry(role_registry_granting("reader", "ca", "list"))
+            .with_auth_identity(AuthorizationIdentity {
+                uid: "alice".to_string(),
+                subuid: Some("dashboard".to_string()),
+                realm: None,
+            });
+        let authorizer = FakeAuthorizer::new(false);
+        let actor = Actor::new(&def, authorizer.clone());
+
+        assert_eq!(actor.is_allowed("list", "ca").unwrap(), false);
+        assert_eq!(authorizer.call_count(), 1);
+    }
+
+    #[cfg(feature = "multi-user")]
+    #[test]
+    fn is_allowed_dispatches_to_authorizer_when_no_role_grants_access() {
+        let def = Actor::user("bob".to_string(), &HashMap::new(), None);
+
+        let allowing = FakeAuthorizer::new(true);
+        let actor = Actor::new(&def, allowing.clone());
+        assert_eq!(actor.is_allowed("list", "ca").unwrap(), true);
+        assert_eq!(allowing.call_count(), 1);
+
+        let denying = FakeAuthorizer::new(false);
+        let actor = Actor::new(&def, denying.clone());
+        assert_eq!(actor.is_allowed("list", "ca").unwrap(), false);
+        assert_eq!(denying.call_count(), 1);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,41 @@
+use std::fmt;
+use std::time::Duration;
+
+/// Errors surfaced across the API boundary. Authorization failures carry
+/// enough structure for the HTTP layer to respond correctly (a
+/// `Retry-After` header, a soft-logout hint) instead of a single opaque
+/// 401.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// The credential presented was rejected outright.
+    ApiInvalidCredentials(String),
+    /// The credential has expired. When `soft_logout` is true the client
+    /// may reuse its existing device/session id to obtain a fresh token.
+    ApiTokenExpired { soft_logout: bool },
+    /// Too many attempts; the client should wait `retry_after` before
+    /// trying again.
+    ApiRateLimited { retry_after: Duration },
+    /// The credential is valid but is not permitted to act at all.
+    ApiForbidden,
+    /// A catch-all for backend-specific failures (e.g. a Casbin enforcer
+    /// error) that don't warrant their own variant.
+    Custom(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::ApiInvalidCredentials(msg) => write!(f, "invalid credentials: {}", msg),
+            Error::ApiTokenExpired { soft_logout } => {
+                write!(f, "token expired (soft_logout={})", soft_logout)
+            }
+            Error::ApiRateLimited { retry_after } => {
+                write!(f, "rate limited, retry after {:?}", retry_after)
+            }
+            Error::ApiForbidden => write!(f, "forbidden"),
+            Error::Custom(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,4 @@
+pub mod actor;
+pub mod error;
+
+pub type KrillResult<T> = Result<T, error::Error>;
@@ -0,0 +1,12 @@
+//! The [`Authorizer`] trait decouples `Actor::is_allowed` from any single
+//! policy engine, so deployments can swap in whichever backend already
+//! holds their policy (Oso/Polar, Casbin, ...) without touching `Actor`.
+
+use crate::commons::actor::Actor;
+use crate::commons::KrillResult;
+
+/// A pluggable authorization backend. Given an actor, a resource and an
+/// action, decides whether the action is permitted.
+pub trait Authorizer: Send + Sync {
+    fn enforce(&self, actor: &Actor, resource: &str, action: &str) -> KrillResult<bool>;
+}
@@ -0,0 +1,4 @@
+pub mod authorizer;
+pub mod casbin;
+pub mod oso;
+pub mod roles;
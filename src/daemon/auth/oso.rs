@@ -0,0 +1,35 @@
+//! [`Authorizer`] implementation backed by the original Oso/Polar policy.
+//! The `ToPolar` bounds that policy evaluation needs are confined to this
+//! module so they don't leak into `Actor::is_allowed`'s public signature.
+
+use crate::commons::actor::Actor;
+use crate::commons::KrillResult;
+use crate::daemon::auth::authorizer::Authorizer;
+use crate::daemon::auth::policy::AuthPolicy;
+
+pub struct OsoAuthorizer {
+    policy: AuthPolicy,
+}
+
+impl OsoAuthorizer {
+    pub fn new(policy: AuthPolicy) -> Self {
+        OsoAuthorizer { policy }
+    }
+}
+
+impl Authorizer for OsoAuthorizer {
+    /// Unlike Casbin's string-principal enforce, the whole `actor` crosses
+    /// into the policy here, so `subuid()`/`realm()` are already available
+    /// to Polar rules as facets of it — no extra plumbing needed for this
+    /// backend.
+    fn enforce(&self, actor: &Actor, resource: &str, action: &str) -> KrillResult<bool> {
+        match self.policy.is_allowed(actor.clone(), action.to_string(), resource.to_string()) {
+            Ok(allowed) => Ok(allowed),
+            Err(err) => {
+                error!("Oso policy evaluation failed: actor={}, action={}, resource={}: {}",
+                    actor.name(), action, resource, err);
+                Ok(false)
+            }
+        }
+    }
+}
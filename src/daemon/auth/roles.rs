@@ -0,0 +1,213 @@
+//! Hierarchical role definitions with permission inheritance.
+//!
+//! A [`Role`] declares its own dotted permission patterns plus a list of
+//! `parents` it inherits from. The [`RoleRegistry`] resolves a role's
+//! *effective* permission set by walking its ancestry, so operators can
+//! express admin/readonly/publisher style hierarchies in config rather than
+//! hand-writing Oso rules for every combination.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+/// A single role: its own permissions plus the roles it inherits from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Role {
+    pub name: String,
+    pub parents: Vec<String>,
+    pub permissions: Vec<String>,
+}
+
+impl Role {
+    pub fn new(name: impl Into<String>, parents: Vec<String>, permissions: Vec<String>) -> Self {
+        Role {
+            name: name.into(),
+            parents,
+            permissions,
+        }
+    }
+}
+
+/// A registry of named [`Role`]s, typically loaded once from config.
+///
+/// `effective_permissions` is on the hot path of every `is_allowed` call,
+/// so resolved permission sets are cached per role name; the cache is
+/// invalidated whenever a role is (re-)added.
+#[derive(Debug, Default)]
+pub struct RoleRegistry {
+    roles: HashMap<String, Role>,
+    effective_permissions_cache: Arc<RwLock<HashMap<String, Vec<String>>>>,
+}
+
+/// Manual impl rather than `#[derive(Clone)]`: a derived clone would share
+/// the `Arc<RwLock<..>>` cache with the original, so mutating one registry's
+/// `roles` via `add_role` (e.g. a config-reload that clones then overrides)
+/// would invalidate and repopulate a cache the other registry still reads
+/// from, silently leaking one instance's resolved permissions into the
+/// other. Each clone gets its own `roles` map and a fresh, empty cache.
+impl Clone for RoleRegistry {
+    fn clone(&self) -> Self {
+        RoleRegistry {
+            roles: self.roles.clone(),
+            effective_permissions_cache: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+}
+
+impl RoleRegistry {
+    pub fn new() -> Self {
+        RoleRegistry {
+            roles: HashMap::new(),
+            effective_permissions_cache: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub fn from_roles(roles: Vec<Role>) -> Self {
+        let mut registry = RoleRegistry::new();
+        for role in roles {
+            registry.add_role(role);
+        }
+        registry
+    }
+
+    pub fn add_role(&mut self, role: Role) {
+        self.roles.insert(role.name.clone(), role);
+        self.effective_permissions_cache.write().unwrap().clear();
+    }
+
+    /// Returns the union of `role_name`'s own permissions with those of all
+    /// of its ancestors, deduped. Cycles in `parents` are broken by a
+    /// visited-set so a misconfigured hierarchy can't recurse forever.
+    pub fn effective_permissions(&self, role_name: &str) -> Vec<String> {
+        if let Some(cached) = self.effective_permissions_cache.read().unwrap().get(role_name) {
+            return cached.clone();
+        }
+
+        let mut visited = HashSet::new();
+        let mut effective = Vec::new();
+        self.collect_permissions(role_name, &mut visited, &mut effective);
+
+        self.effective_permissions_cache.write().unwrap().insert(role_name.to_string(), effective.clone());
+        effective
+    }
+
+    fn collect_permissions(&self, role_name: &str, visited: &mut HashSet<String>, effective: &mut Vec<String>) {
+        if !visited.insert(role_name.to_string()) {
+            return;
+        }
+
+        if let Some(role) = self.roles.get(role_name) {
+            for parent in &role.parents {
+                self.collect_permissions(parent, visited, effective);
+            }
+            for permission in &role.permissions {
+                if !effective.contains(permission) {
+                    effective.push(permission.clone());
+                }
+            }
+        }
+    }
+
+    /// Returns true if `role_name`'s effective permissions grant access to
+    /// `resource_type`/`action`, e.g. `resource_type` "ca" and `action`
+    /// "child.add" is granted by the permission "ca.*" or "ca.child.add".
+    pub fn is_permitted(&self, role_name: &str, resource_type: &str, action: &str) -> bool {
+        let key = format!("{}.{}", resource_type, action);
+        self.effective_permissions(role_name)
+            .iter()
+            .any(|pattern| permission_matches(pattern, &key))
+    }
+}
+
+/// Matches a dotted permission pattern against a dotted key, honouring a
+/// trailing `*` segment that matches any (one or more) remaining segments.
+fn permission_matches(pattern: &str, key: &str) -> bool {
+    let pattern_segments: Vec<&str> = pattern.split('.').collect();
+    let key_segments: Vec<&str> = key.split('.').collect();
+
+    for (i, segment) in pattern_segments.iter().enumerate() {
+        if *segment == "*" {
+            return i < key_segments.len();
+        }
+        match key_segments.get(i) {
+            Some(k) if k == segment => continue,
+            _ => return false,
+        }
+    }
+
+    pattern_segments.len() == key_segments.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> RoleRegistry {
+        RoleRegistry::from_roles(vec![
+            Role::new("readonly", vec![], vec!["ca.*".to_string(), "bgpsec.list".to_string()]),
+            Role::new(
+                "publisher",
+                vec!["readonly".to_string()],
+                vec!["publication.add".to_string()],
+            ),
+            Role::new("admin", vec!["publisher".to_string()], vec!["system.*".to_string()]),
+        ])
+    }
+
+    #[test]
+    fn effective_permissions_include_inherited() {
+        let registry = registry();
+        let admin_permissions = registry.effective_permissions("admin");
+        assert!(admin_permissions.contains(&"system.*".to_string()));
+        assert!(admin_permissions.contains(&"publication.add".to_string()));
+        assert!(admin_permissions.contains(&"ca.*".to_string()));
+    }
+
+    #[test]
+    fn wildcard_matches_nested_segments() {
+        let registry = registry();
+        assert!(registry.is_permitted("readonly", "ca", "child.add"));
+        assert!(registry.is_permitted("readonly", "ca", "child.remove"));
+        assert!(!registry.is_permitted("readonly", "publication", "add"));
+        assert!(registry.is_permitted("publisher", "publication", "add"));
+    }
+
+    #[test]
+    fn cycles_in_parents_do_not_recurse_forever() {
+        let mut registry = RoleRegistry::new();
+        registry.add_role(Role::new("a", vec!["b".to_string()], vec!["a.one".to_string()]));
+        registry.add_role(Role::new("b", vec!["a".to_string()], vec!["b.one".to_string()]));
+
+        let permissions = registry.effective_permissions("a");
+        assert_eq!(permissions.len(), 2);
+        assert!(permissions.contains(&"a.one".to_string()));
+        assert!(permissions.contains(&"b.one".to_string()));
+    }
+
+    #[test]
+    fn duplicate_permissions_across_ancestors_are_deduped() {
+        let mut registry = RoleRegistry::new();
+        registry.add_role(Role::new("base", vec![], vec!["ca.list".to_string()]));
+        registry.add_role(Role::new(
+            "mid",
+            vec!["base".to_string()],
+            vec!["ca.list".to_string()],
+        ));
+
+        assert_eq!(registry.effective_permissions("mid"), vec!["ca.list".to_string()]);
+    }
+
+    #[test]
+    fn cloned_registries_do_not_share_a_cache() {
+        let mut original = registry();
+        original.effective_permissions("readonly");
+
+        let mut cloned = original.clone();
+        cloned.add_role(Role::new("readonly", vec![], vec!["override.*".to_string()]));
+
+        assert_eq!(
+            original.effective_permissions("readonly"),
+            vec!["ca.*".to_string(), "bgpsec.list".to_string()]
+        );
+        assert_eq!(cloned.effective_permissions("readonly"), vec!["override.*".to_string()]);
+    }
+}
@@ -0,0 +1,67 @@
+//! Maps an authorization [`Error`] onto the HTTP response it implies, so a
+//! rate-limited or soft-logout-eligible client gets more than an opaque
+//! 401: a `Retry-After` header for backoff, or a hint that it may reuse
+//! its existing device/session id instead of a full re-login.
+
+use hyper::{Body, Response, StatusCode};
+
+use crate::commons::error::Error;
+
+/// Header used to signal that a `TokenExpired` failure is soft: the client
+/// may retry with its existing device/session id rather than re-logging in
+/// from scratch.
+pub const SOFT_LOGOUT_HEADER: &str = "X-Krill-Soft-Logout";
+
+pub fn auth_error_response(err: &Error) -> Response<Body> {
+    let (status, body) = match err {
+        Error::ApiInvalidCredentials(msg) => (StatusCode::UNAUTHORIZED, msg.clone()),
+        Error::ApiTokenExpired { .. } => (StatusCode::UNAUTHORIZED, "token expired".to_string()),
+        Error::ApiRateLimited { .. } => (StatusCode::TOO_MANY_REQUESTS, "rate limited".to_string()),
+        Error::ApiForbidden => (StatusCode::FORBIDDEN, "forbidden".to_string()),
+        Error::Custom(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg.clone()),
+    };
+
+    let mut builder = Response::builder().status(status);
+
+    if let Error::ApiRateLimited { retry_after } = err {
+        // Round up rather than truncate: a sub-second `retry_after` must
+        // still advertise at least 1 second, or the client reads "0" and
+        // retries immediately instead of backing off.
+        let retry_after_secs = retry_after.as_secs_f64().ceil() as u64;
+        builder = builder.header(hyper::header::RETRY_AFTER, retry_after_secs.max(1).to_string());
+    }
+
+    if let Error::ApiTokenExpired { soft_logout: true } = err {
+        builder = builder.header(SOFT_LOGOUT_HEADER, "true");
+    }
+
+    builder.body(Body::from(body)).expect("static status and header values are always valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn retry_after_header(err: &Error) -> String {
+        auth_error_response(err)
+            .headers()
+            .get(hyper::header::RETRY_AFTER)
+            .expect("Retry-After header set")
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn sub_second_retry_after_rounds_up_to_one() {
+        let err = Error::ApiRateLimited { retry_after: Duration::from_millis(500) };
+        assert_eq!(retry_after_header(&err), "1");
+    }
+
+    #[test]
+    fn whole_second_retry_after_is_unchanged() {
+        let err = Error::ApiRateLimited { retry_after: Duration::from_secs(30) };
+        assert_eq!(retry_after_header(&err), "30");
+    }
+}